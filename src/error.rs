@@ -0,0 +1,35 @@
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum Error {
+    FileWatch(notify::Error),
+    /// The file a `Watch`/`WatchStream` was compiling from was removed or renamed away.
+    SourceRemoved(PathBuf),
+    /// A `#include` directive couldn't be resolved against the including file's own
+    /// directory or any of the configured include directories.
+    IncludeNotFound(PathBuf),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::FileWatch(e) => write!(f, "failed to watch shader source: {}", e),
+            Error::SourceRemoved(path) => {
+                write!(f, "shader source removed: {}", path.display())
+            }
+            Error::IncludeNotFound(path) => {
+                write!(f, "could not resolve #include \"{}\"", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::FileWatch(e) => Some(e),
+            Error::SourceRemoved(_) | Error::IncludeNotFound(_) => None,
+        }
+    }
+}
@@ -1,37 +1,97 @@
 use crate::error::Error;
 use crate::layouts::Entry;
 use crate::CompiledShaders;
-use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use notify::{PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use futures::channel::mpsc as futures_mpsc;
+use futures::Stream;
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
+use std::task::{Context, Poll};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub struct Watch {
     _handler: Handler,
     pub rx: Receiver<Result<Message, Error>>,
 }
 
+/// Which notify backend to use when watching for shader changes.
+///
+/// `Native` relies on the OS's file event API (inotify, FSEvents, ReadDirectoryChangesW)
+/// and is cheap, but silently misses events on network shares, WSL mounts, and some
+/// Docker bind mounts. `Poll` scans the watched directories on an interval instead, which
+/// is slower but works everywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchBackend {
+    Native,
+    Poll,
+}
+
 enum Loader {
     Graphics(GraphicsLoader),
     Compute(ComputeLoader),
 }
 
+/// `notify::Watcher` is generic over its `watch`/`unwatch` methods, so it isn't
+/// dyn-compatible. Pick the concrete backend with an enum instead, mirroring
+/// the `Loader` and `SrcPath` enums.
+enum AnyWatcher {
+    Native(RecommendedWatcher),
+    Poll(PollWatcher),
+}
+
+impl AnyWatcher {
+    fn watch(&mut self, path: &Path, mode: RecursiveMode) -> Result<(), notify::Error> {
+        match self {
+            AnyWatcher::Native(w) => w.watch(path, mode),
+            AnyWatcher::Poll(w) => w.watch(path, mode),
+        }
+    }
+}
+
 enum SrcPath {
     Graphics(PathBuf, PathBuf),
     Compute(PathBuf),
 }
 
+/// Where a `Loader` delivers reload results. `Std` backs the blocking `Watch`
+/// API; `Futures` lets `WatchStream` receive reloads directly from the watch
+/// thread with no bridging thread in between.
+enum ReloadSink {
+    Std(Sender<Result<Message, Error>>),
+    Futures(futures_mpsc::UnboundedSender<Result<Message, Error>>),
+}
+
+impl ReloadSink {
+    fn send(&self, msg: Result<Message, Error>) {
+        match self {
+            ReloadSink::Std(tx) => {
+                tx.send(msg).ok();
+            }
+            ReloadSink::Futures(tx) => {
+                tx.unbounded_send(msg).ok();
+            }
+        }
+    }
+}
+
 struct GraphicsLoader {
     vertex: PathBuf,
     fragment: PathBuf,
-    tx: Sender<Result<Message, Error>>,
+    include_dirs: Vec<PathBuf>,
+    deps: RefCell<HashSet<PathBuf>>,
+    tx: ReloadSink,
 }
 
 struct ComputeLoader {
     compute: PathBuf,
-    tx: Sender<Result<Message, Error>>,
+    include_dirs: Vec<PathBuf>,
+    deps: RefCell<HashSet<PathBuf>>,
+    tx: ReloadSink,
 }
 
 pub struct Message {
@@ -43,6 +103,24 @@ impl Watch {
     /// Paths to the vertex and fragment shaders.
     /// Frequency is how often the watcher will check the directory.
     pub fn create<T>(vertex: T, fragment: T, frequency: Duration) -> Result<Self, Error>
+    where
+        T: AsRef<Path>,
+    {
+        Self::create_with(vertex, fragment, frequency, WatchBackend::Native, Vec::new())
+    }
+
+    /// Same as `create`, but lets the caller pick the notify backend and a list of
+    /// directories to search when resolving `#include` directives (in addition to
+    /// each including file's own directory). Use `WatchBackend::Poll` when watching
+    /// a path on a network share or other filesystem where native events aren't
+    /// delivered reliably.
+    pub fn create_with<T>(
+        vertex: T,
+        fragment: T,
+        frequency: Duration,
+        backend: WatchBackend,
+        include_dirs: Vec<PathBuf>,
+    ) -> Result<Self, Error>
     where
         T: AsRef<Path>,
     {
@@ -50,9 +128,13 @@ impl Watch {
             vertex.as_ref().to_path_buf(),
             fragment.as_ref().to_path_buf()
             );
-        let (handler, rx) = create_watch(
+        let (tx, rx) = mpsc::channel();
+        let handler = create_watch(
             src_path,
             frequency,
+            backend,
+            include_dirs,
+            ReloadSink::Std(tx),
         )?;
         Ok(Watch {
             _handler: handler,
@@ -61,67 +143,231 @@ impl Watch {
     }
 
     pub fn create_compute<T>(compute: T, frequency: Duration) -> Result<Self, Error>
+    where
+        T: AsRef<Path>,
+    {
+        Self::create_compute_with(compute, frequency, WatchBackend::Native, Vec::new())
+    }
+
+    /// Same as `create_compute`, but lets the caller pick the notify backend and
+    /// the `#include` search directories.
+    pub fn create_compute_with<T>(
+        compute: T,
+        frequency: Duration,
+        backend: WatchBackend,
+        include_dirs: Vec<PathBuf>,
+    ) -> Result<Self, Error>
     where
         T: AsRef<Path>,
     {
         let src_path = SrcPath::Compute(
             compute.as_ref(). to_path_buf());
-        let (handler, rx) = create_watch(
+        let (tx, rx) = mpsc::channel();
+        let handler = create_watch(
             src_path,
             frequency,
+            backend,
+            include_dirs,
+            ReloadSink::Std(tx),
         )?;
         Ok(Watch {
             _handler: handler,
             rx,
         })
     }
+
+    /// Same as `create_with`, but returns an async `Stream` of reload results
+    /// instead of a blocking `mpsc::Receiver`, so an async render loop can
+    /// `.await` shader reloads directly inside an async frame scheduler. The
+    /// watch thread delivers straight into a `futures::channel::mpsc` sender,
+    /// so there's no extra bridging thread beyond the one watch thread shared
+    /// with the blocking API.
+    pub fn create_stream<T>(
+        vertex: T,
+        fragment: T,
+        frequency: Duration,
+        backend: WatchBackend,
+        include_dirs: Vec<PathBuf>,
+    ) -> Result<WatchStream, Error>
+    where
+        T: AsRef<Path>,
+    {
+        let src_path = SrcPath::Graphics(
+            vertex.as_ref().to_path_buf(),
+            fragment.as_ref().to_path_buf()
+            );
+        WatchStream::create(src_path, frequency, backend, include_dirs)
+    }
+
+    /// Same as `create_compute_with`, but returns an async `Stream` of reload
+    /// results instead of a blocking `mpsc::Receiver`.
+    pub fn create_compute_stream<T>(
+        compute: T,
+        frequency: Duration,
+        backend: WatchBackend,
+        include_dirs: Vec<PathBuf>,
+    ) -> Result<WatchStream, Error>
+    where
+        T: AsRef<Path>,
+    {
+        let src_path = SrcPath::Compute(compute.as_ref().to_path_buf());
+        WatchStream::create(src_path, frequency, backend, include_dirs)
+    }
+}
+
+/// An async `Stream` of shader reloads, built on the same loader,
+/// include-resolution, and debounce logic as the blocking `Watch` API, but fed
+/// directly from the watch thread via a `futures::channel::mpsc` sender.
+pub struct WatchStream {
+    handler: Option<Handler>,
+    rx: futures_mpsc::UnboundedReceiver<Result<Message, Error>>,
+}
+
+impl WatchStream {
+    fn create(
+        src_path: SrcPath,
+        frequency: Duration,
+        backend: WatchBackend,
+        include_dirs: Vec<PathBuf>,
+    ) -> Result<Self, Error> {
+        let (tx, rx) = futures_mpsc::unbounded();
+        let handler = create_watch(
+            src_path,
+            frequency,
+            backend,
+            include_dirs,
+            ReloadSink::Futures(tx),
+        )?;
+        Ok(WatchStream {
+            handler: Some(handler),
+            rx,
+        })
+    }
+}
+
+impl Stream for WatchStream {
+    type Item = Result<Message, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.rx).poll_next(cx)
+    }
+}
+
+impl Drop for WatchStream {
+    fn drop(&mut self) {
+        // Dropping the handler stops the watch thread, which drops its
+        // `ReloadSink::Futures` sender and closes `rx` in turn.
+        self.handler.take();
+    }
 }
 
 impl GraphicsLoader {
-    fn create(vertex: PathBuf, fragment: PathBuf) -> (Self, Receiver<Result<Message, Error>>) {
-        let (tx, rx) = mpsc::channel();
+    fn create(vertex: PathBuf, fragment: PathBuf, include_dirs: Vec<PathBuf>, tx: ReloadSink) -> Self {
         let loader = GraphicsLoader {
             vertex,
             fragment,
+            include_dirs,
+            deps: RefCell::new(HashSet::new()),
             tx,
         };
         loader.reload();
-        (loader, rx)
+        loader
     }
 
     fn reload(&self) {
+        self.refresh_deps();
         match crate::load(&self.vertex, &self.fragment) {
             Ok(shaders) => {
                 let entry = crate::parse(&shaders);
                 let msg = entry.map(|entry| Message { shaders, entry });
-                self.tx.send(msg).ok()
+                self.tx.send(msg)
             }
-            Err(e) => self.tx.send(Err(e)).ok(),
+            Err(e) => self.tx.send(Err(e)),
         };
     }
+
+    fn send_error(&self, err: Error) {
+        self.tx.send(Err(err));
+    }
+
+    fn tracks(&self, path: &Path) -> bool {
+        same_file(path, &self.vertex)
+            || same_file(path, &self.fragment)
+            || self.deps.borrow().iter().any(|dep| same_file(path, dep))
+    }
+
+    /// Re-resolves `#include` directives reachable from the vertex and fragment
+    /// shaders, since edits can add or remove includes between reloads.
+    fn refresh_deps(&self) {
+        let mut visited = HashSet::new();
+        let mut deps = HashSet::new();
+        for src in [&self.vertex, &self.fragment] {
+            match resolve_includes(src, &self.include_dirs, &mut visited) {
+                Ok(found) => deps.extend(found),
+                Err(e) => {
+                    self.tx.send(Err(e));
+                }
+            }
+        }
+        *self.deps.borrow_mut() = deps;
+    }
+
+    fn tracked_paths(&self) -> Vec<PathBuf> {
+        let mut paths = vec![self.vertex.clone(), self.fragment.clone()];
+        paths.extend(self.deps.borrow().iter().cloned());
+        paths
+    }
 }
 
 impl ComputeLoader {
-    fn create(compute: PathBuf) -> (Self, Receiver<Result<Message, Error>>) {
-        let (tx, rx) = mpsc::channel();
+    fn create(compute: PathBuf, include_dirs: Vec<PathBuf>, tx: ReloadSink) -> Self {
         let loader = ComputeLoader {
             compute,
+            include_dirs,
+            deps: RefCell::new(HashSet::new()),
             tx,
         };
         loader.reload();
-        (loader, rx)
+        loader
     }
 
     fn reload(&self) {
+        self.refresh_deps();
         match crate::load_compute(&self.compute) {
             Ok(shaders) => {
                 let entry = crate::parse_compute(&shaders);
                 let msg = entry.map(|entry| Message { shaders, entry });
-                self.tx.send(msg).ok()
+                self.tx.send(msg)
             }
-            Err(e) => self.tx.send(Err(e)).ok(),
+            Err(e) => self.tx.send(Err(e)),
         };
     }
+
+    fn send_error(&self, err: Error) {
+        self.tx.send(Err(err));
+    }
+
+    fn tracks(&self, path: &Path) -> bool {
+        same_file(path, &self.compute) || self.deps.borrow().iter().any(|dep| same_file(path, dep))
+    }
+
+    /// Re-resolves `#include` directives reachable from the compute shader, since
+    /// edits can add or remove includes between reloads.
+    fn refresh_deps(&self) {
+        let mut visited = HashSet::new();
+        match resolve_includes(&self.compute, &self.include_dirs, &mut visited) {
+            Ok(deps) => *self.deps.borrow_mut() = deps,
+            Err(e) => {
+                self.tx.send(Err(e));
+            }
+        }
+    }
+
+    fn tracked_paths(&self) -> Vec<PathBuf> {
+        let mut paths = vec![self.compute.clone()];
+        paths.extend(self.deps.borrow().iter().cloned());
+        paths
+    }
 }
 
 impl Loader {
@@ -131,12 +377,98 @@ impl Loader {
             Loader::Compute(g) => g.reload(),
         }
     }
+
+    fn send_error(&self, err: Error) {
+        match self {
+            Loader::Graphics(g) => g.send_error(err),
+            Loader::Compute(g) => g.send_error(err),
+        }
+    }
+
+    /// Whether `path` is one of the files this loader compiles from, or one of
+    /// their transitive `#include` dependencies.
+    fn tracks(&self, path: &Path) -> bool {
+        match self {
+            Loader::Graphics(g) => g.tracks(path),
+            Loader::Compute(g) => g.tracks(path),
+        }
+    }
+
+    /// All files that should be watched for this loader: its own source(s) plus
+    /// every file currently known to be `#include`d by them.
+    fn tracked_paths(&self) -> Vec<PathBuf> {
+        match self {
+            Loader::Graphics(g) => g.tracked_paths(),
+            Loader::Compute(g) => g.tracked_paths(),
+        }
+    }
+}
+
+/// Scans `path` for `#include "..."` / `#include <...>` directives and resolves
+/// them against the including file's own directory, then `include_dirs`,
+/// recursing to build the transitive set of included files. `visited` guards
+/// against include cycles across the whole resolution pass.
+fn resolve_includes(
+    path: &Path,
+    include_dirs: &[PathBuf],
+    visited: &mut HashSet<PathBuf>,
+) -> Result<HashSet<PathBuf>, Error> {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if !visited.insert(canonical.clone()) {
+        return Ok(HashSet::new());
+    }
+
+    let mut deps = HashSet::new();
+    deps.insert(canonical);
+
+    let src = match std::fs::read_to_string(path) {
+        Ok(src) => src,
+        Err(_) => return Ok(deps),
+    };
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    for line in src.lines() {
+        let line = line.trim();
+        let rest = match line.strip_prefix("#include") {
+            Some(rest) => rest.trim(),
+            None => continue,
+        };
+        let name = if let Some(name) = rest.strip_prefix('"').and_then(|r| r.strip_suffix('"')) {
+            name
+        } else if let Some(name) = rest.strip_prefix('<').and_then(|r| r.strip_suffix('>')) {
+            name
+        } else {
+            continue;
+        };
+
+        let resolved = std::iter::once(dir.to_path_buf())
+            .chain(include_dirs.iter().cloned())
+            .map(|base| base.join(name))
+            .find(|candidate| candidate.exists());
+
+        match resolved {
+            Some(included) => deps.extend(resolve_includes(&included, include_dirs, visited)?),
+            None => return Err(Error::IncludeNotFound(PathBuf::from(name))),
+        }
+    }
+
+    Ok(deps)
+}
+
+/// Compares two paths as the same file, canonicalizing first so that e.g.
+/// symlinks and relative/absolute forms of the same path compare equal.
+/// Falls back to a plain comparison if either path can no longer be
+/// canonicalized (for example, it was just deleted).
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
 }
 
 struct Handler {
     thread_tx: mpsc::Sender<()>,
     handle: Option<thread::JoinHandle<()>>,
-    _watcher: RecommendedWatcher,
 }
 
 impl Drop for Handler {
@@ -148,61 +480,304 @@ impl Drop for Handler {
     }
 }
 
+/// Collapses a burst of relevant filesystem events into a single reload.
+/// Each relevant event pushes the quiescent deadline `frequency` further out;
+/// the burst is "due" only once that deadline has actually passed without a
+/// further event resetting it.
+struct Coalescer {
+    quiescent_at: Option<Instant>,
+}
+
+impl Coalescer {
+    fn new() -> Self {
+        Coalescer { quiescent_at: None }
+    }
+
+    /// Record a relevant event at `now`, resetting the quiescent window.
+    fn mark_dirty(&mut self, now: Instant, frequency: Duration) {
+        self.quiescent_at = Some(now + frequency);
+    }
+
+    /// How long the watch loop should block waiting for the next event.
+    fn timeout(&self, now: Instant) -> Duration {
+        match self.quiescent_at {
+            Some(at) => at.saturating_duration_since(now),
+            None => Duration::from_secs(1),
+        }
+    }
+
+    /// If a burst is pending and has gone quiescent as of `now`, clear it and
+    /// return true. Returns false both when nothing is pending and when the
+    /// window hasn't elapsed yet.
+    fn take_due(&mut self, now: Instant) -> bool {
+        match self.quiescent_at {
+            Some(at) if now >= at => {
+                self.quiescent_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Watches the parent directory of every file in `loader`'s current dependency
+/// set that isn't already watched, recording it in `watched_dirs` so later calls
+/// (after a reload changes the include graph) only add what's new.
+fn watch_dependency_dirs(
+    watcher: &mut AnyWatcher,
+    loader: &Loader,
+    watched_dirs: &mut HashSet<PathBuf>,
+) -> Result<(), Error> {
+    for path in loader.tracked_paths() {
+        let dir = match path.parent() {
+            Some(dir) => dir.to_path_buf(),
+            None => continue,
+        };
+        if watched_dirs.insert(dir.clone()) {
+            watcher
+                .watch(&dir, RecursiveMode::NonRecursive)
+                .map_err(Error::FileWatch)?;
+        }
+    }
+    Ok(())
+}
+
 fn create_watch(
     src_path: SrcPath,
-    frequency: Duration
-) -> Result<(Handler, mpsc::Receiver<Result<Message, Error>>), Error> {
+    frequency: Duration,
+    backend: WatchBackend,
+    include_dirs: Vec<PathBuf>,
+    sink: ReloadSink,
+) -> Result<Handler, Error> {
     let (notify_tx, notify_rx) = mpsc::channel();
     let (thread_tx, thread_rx) = mpsc::channel();
-    let mut watcher: RecommendedWatcher =
-        Watcher::new(notify_tx, frequency).map_err(Error::FileWatch)?;
+    let mut watcher = match backend {
+        WatchBackend::Native => {
+            let watcher: RecommendedWatcher =
+                Watcher::new(notify_tx, frequency).map_err(Error::FileWatch)?;
+            AnyWatcher::Native(watcher)
+        }
+        WatchBackend::Poll => {
+            let watcher: PollWatcher =
+                Watcher::new(notify_tx, frequency).map_err(Error::FileWatch)?;
+            AnyWatcher::Poll(watcher)
+        }
+    };
 
-    let (loader, rx) = match src_path {
+    let loader = match src_path {
         SrcPath::Graphics(vert_path, frag_path) => {
-            let mut vp = vert_path.clone();
-            let mut fp = frag_path.clone();
-            vp.pop();
-            fp.pop();
-            watcher
-                .watch(&vp, RecursiveMode::NonRecursive)
-                .map_err(Error::FileWatch)?;
-            if vp != fp {
-                watcher
-                    .watch(&fp, RecursiveMode::NonRecursive)
-                    .map_err(Error::FileWatch)?;
-            }
-
-            let (loader, rx) = GraphicsLoader::create(vert_path, frag_path);
-            (Loader::Graphics(loader), rx)
+            Loader::Graphics(GraphicsLoader::create(vert_path, frag_path, include_dirs, sink))
         }
         SrcPath::Compute(compute_path) => {
-            let mut cp = compute_path.clone();
-            cp.pop();
-            watcher
-                .watch(&cp, RecursiveMode::NonRecursive)
-                .map_err(Error::FileWatch)?;
-
-            let (loader, rx) = ComputeLoader::create(compute_path);
-            (Loader::Compute(loader), rx)
+            Loader::Compute(ComputeLoader::create(compute_path, include_dirs, sink))
         }
     };
 
+    let mut watched_dirs = HashSet::new();
+    watch_dependency_dirs(&mut watcher, &loader, &mut watched_dirs)?;
 
-    let handle = thread::spawn(move || 'watch_loop: loop {
-        if thread_rx.try_recv().is_ok() {
-            break 'watch_loop;
-        }
-        if let Ok(notify::DebouncedEvent::Create(_)) | Ok(notify::DebouncedEvent::Write(_)) =
-            notify_rx.recv_timeout(Duration::from_secs(1))
-        {
-            loader.reload();
+    // Rapid saves (and truncate-then-write sequences) can produce several
+    // Create/Write events for what is really one edit. Rather than recompiling
+    // on each one, let a `Coalescer` collapse a burst into a single reload of
+    // the settled file.
+    let handle = thread::spawn(move || {
+        let mut coalescer = Coalescer::new();
+        'watch_loop: loop {
+            if thread_rx.try_recv().is_ok() {
+                break 'watch_loop;
+            }
+            let timeout = coalescer.timeout(Instant::now());
+            match notify_rx.recv_timeout(timeout) {
+                Ok(notify::DebouncedEvent::Create(path)) | Ok(notify::DebouncedEvent::Write(path))
+                    if loader.tracks(&path) =>
+                {
+                    coalescer.mark_dirty(Instant::now(), frequency);
+                }
+                Ok(notify::DebouncedEvent::Remove(path)) | Ok(notify::DebouncedEvent::Rename(path, _))
+                    if loader.tracks(&path) =>
+                {
+                    loader.send_error(Error::SourceRemoved(path));
+                }
+                Ok(notify::DebouncedEvent::Rescan) => {
+                    // The backend may have dropped individual events, so do a full reload
+                    // rather than trusting that we saw everything that changed.
+                    coalescer.mark_dirty(Instant::now(), frequency);
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) if coalescer.take_due(Instant::now()) => {
+                    loader.reload();
+                    watch_dependency_dirs(&mut watcher, &loader, &mut watched_dirs).ok();
+                }
+                _ => {}
+            }
         }
     });
     let handle = Some(handle);
-    let handler = Handler {
-        thread_tx,
-        handle,
-        _watcher: watcher,
-    };
-    Ok((handler, rx))
+    Ok(Handler { thread_tx, handle })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A fresh temp directory for one test, cleaned up on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(tag: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "shade_runner_watch_test_{}_{}_{}",
+                tag,
+                std::process::id(),
+                TempDir::next_id(),
+            ));
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn next_id() -> usize {
+            use std::sync::atomic::{AtomicUsize, Ordering};
+            static NEXT: AtomicUsize = AtomicUsize::new(0);
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        }
+
+        fn path(&self) -> &Path {
+            &self.0
+        }
+
+        fn write(&self, name: &str, contents: &str) -> PathBuf {
+            let path = self.0.join(name);
+            fs::write(&path, contents).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    #[test]
+    fn resolve_includes_follows_transitive_includes() {
+        let dir = TempDir::new("transitive");
+        dir.write("math.glsl", "float square(float x) { return x * x; }");
+        dir.write("lighting.glsl", "#include \"math.glsl\"\nvec3 light() { return vec3(0.0); }");
+        let main = dir.write("main.vert", "#include \"lighting.glsl\"\nvoid main() {}");
+
+        let mut visited = HashSet::new();
+        let deps = resolve_includes(&main, &[], &mut visited).unwrap();
+
+        assert!(deps.contains(&main.canonicalize().unwrap()));
+        assert!(deps.contains(&dir.path().join("lighting.glsl").canonicalize().unwrap()));
+        assert!(deps.contains(&dir.path().join("math.glsl").canonicalize().unwrap()));
+    }
+
+    #[test]
+    fn resolve_includes_searches_include_dirs_after_own_directory() {
+        let dir = TempDir::new("include_dirs");
+        let shared = TempDir::new("include_dirs_shared");
+        shared.write("common.glsl", "struct Common {};");
+        let main = dir.write("main.frag", "#include <common.glsl>\nvoid main() {}");
+
+        let mut visited = HashSet::new();
+        let deps =
+            resolve_includes(&main, &[shared.path().to_path_buf()], &mut visited).unwrap();
+
+        assert!(deps.contains(&shared.path().join("common.glsl").canonicalize().unwrap()));
+    }
+
+    #[test]
+    fn resolve_includes_terminates_on_cycle() {
+        let dir = TempDir::new("cycle");
+        dir.write("a.glsl", "#include \"b.glsl\"");
+        dir.write("b.glsl", "#include \"a.glsl\"");
+        let a = dir.path().join("a.glsl");
+
+        let mut visited = HashSet::new();
+        let deps = resolve_includes(&a, &[], &mut visited).unwrap();
+
+        assert!(deps.contains(&a.canonicalize().unwrap()));
+        assert!(deps.contains(&dir.path().join("b.glsl").canonicalize().unwrap()));
+    }
+
+    #[test]
+    fn resolve_includes_reports_missing_include() {
+        let dir = TempDir::new("missing");
+        let main = dir.write("main.vert", "#include \"does_not_exist.glsl\"\nvoid main() {}");
+
+        let mut visited = HashSet::new();
+        let err = resolve_includes(&main, &[], &mut visited).unwrap_err();
+
+        match err {
+            Error::IncludeNotFound(path) => assert_eq!(path, PathBuf::from("does_not_exist.glsl")),
+            other => panic!("expected IncludeNotFound, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn same_file_compares_canonicalized_paths() {
+        let dir = TempDir::new("same_file");
+        let path = dir.write("a.glsl", "");
+        let relative = dir.path().join(".").join("a.glsl");
+
+        assert!(same_file(&path, &relative));
+        assert!(!same_file(&path, &dir.path().join("b.glsl")));
+    }
+
+    #[test]
+    fn same_file_falls_back_when_one_side_cannot_be_canonicalized() {
+        let dir = TempDir::new("same_file_missing");
+        let path = dir.write("a.glsl", "");
+        let missing = dir.path().join("does_not_exist.glsl");
+
+        assert!(!same_file(&path, &missing));
+        assert!(same_file(&missing, &missing));
+    }
+
+    fn test_sink() -> ReloadSink {
+        ReloadSink::Std(mpsc::channel().0)
+    }
+
+    #[test]
+    fn graphics_loader_tracks_its_own_files_and_deps() {
+        let dir = TempDir::new("tracks");
+        let vertex = dir.write("main.vert", "");
+        let fragment = dir.write("main.frag", "");
+        let header = dir.write("common.glsl", "");
+        let unrelated = dir.write("notes.txt", "");
+
+        let loader = GraphicsLoader {
+            vertex: vertex.clone(),
+            fragment: fragment.clone(),
+            include_dirs: Vec::new(),
+            deps: RefCell::new(HashSet::from([header.clone()])),
+            tx: test_sink(),
+        };
+
+        assert!(loader.tracks(&vertex));
+        assert!(loader.tracks(&fragment));
+        assert!(loader.tracks(&header));
+        assert!(!loader.tracks(&unrelated));
+    }
+
+    #[test]
+    fn coalescer_collapses_a_burst_into_one_reload() {
+        let frequency = Duration::from_millis(100);
+        let mut coalescer = Coalescer::new();
+        let t0 = Instant::now();
+
+        coalescer.mark_dirty(t0, frequency);
+        assert!(!coalescer.take_due(t0));
+
+        // A second event arrives mid-window and resets the deadline.
+        let t1 = t0 + Duration::from_millis(50);
+        coalescer.mark_dirty(t1, frequency);
+        assert!(!coalescer.take_due(t0 + frequency));
+
+        // Quiescent for a full `frequency` since the last event: due, once.
+        let fire_at = t1 + frequency;
+        assert!(coalescer.take_due(fire_at));
+        assert!(!coalescer.take_due(fire_at + frequency));
+    }
 }